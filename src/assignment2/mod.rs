@@ -15,9 +15,13 @@ use self::{
     watch_list::WatchList,
 };
 use crate::{
-    clause_db::ClauseRef,
+    clause_db::{
+        ClauseId,
+        ClauseRef,
+    },
     utils::{
         bounded_map,
+        BoundedBitmap,
         BoundedMap,
     },
     ClauseDb,
@@ -26,7 +30,10 @@ use crate::{
     VarAssignment,
     Variable,
 };
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    iter,
+};
 
 /// Errors that may be encountered when operating on the assignment.
 #[derive(Debug)]
@@ -51,7 +58,7 @@ impl<'a> PropagationEnqueuer<'a> {
         Self { queue }
     }
 
-    /// Enqueues a new literal to the propagation queue.
+    /// Enqueues a new literal to the propagation queue, implied by `reason`.
     ///
     /// # Errors
     ///
@@ -61,9 +68,10 @@ impl<'a> PropagationEnqueuer<'a> {
     pub fn push(
         &mut self,
         literal: Literal,
+        reason: ClauseId,
         assignment: &VariableAssignment,
     ) -> Result<(), EnqueueError> {
-        self.queue.push(literal, assignment)
+        self.queue.push(literal, Some(reason), assignment)
     }
 }
 
@@ -85,12 +93,15 @@ impl EnqueueError {
 
 #[derive(Debug, Default, Clone)]
 pub struct PropagationQueue {
-    queue: VecDeque<Literal>,
+    queue: VecDeque<(Literal, Option<ClauseId>)>,
 }
 
 impl PropagationQueue {
     /// Pushes another literal to the propagation queue.
     ///
+    /// `reason` is `None` for decision literals and `Some` for literals
+    /// implied by unit propagation of the given clause.
+    ///
     /// # Errors
     ///
     /// - If the literal has already been satisfied.
@@ -99,6 +110,7 @@ impl PropagationQueue {
     pub fn push(
         &mut self,
         literal: Literal,
+        reason: Option<ClauseId>,
         assignment: &VariableAssignment,
     ) -> Result<(), EnqueueError> {
         match assignment.get(literal.variable()) {
@@ -108,14 +120,14 @@ impl PropagationQueue {
                 Err(EnqueueError::Conflict)
             }
             None => {
-                self.queue.push_back(literal);
+                self.queue.push_back((literal, reason));
                 Ok(())
             }
         }
     }
 
-    /// Pops the next propagation literal from the propagation queue.
-    pub fn pop(&mut self) -> Option<Literal> {
+    /// Pops the next propagation literal and its reason from the propagation queue.
+    pub fn pop(&mut self) -> Option<(Literal, Option<ClauseId>)> {
         self.queue.pop_front()
     }
 }
@@ -124,6 +136,13 @@ impl PropagationQueue {
 #[derive(Debug, Default, Clone)]
 pub struct VariableAssignment {
     assignment: BoundedMap<Variable, VarAssignment>,
+    /// The polarity a variable was last assigned to.
+    ///
+    /// This is used by the decision heuristic for phase saving: once a
+    /// variable is picked for a new decision, branching on its last value
+    /// instead of a fixed default avoids undoing propagation work that is
+    /// likely to be redone anyway.
+    last_values: BoundedBitmap<Variable, bool>,
 }
 
 impl VariableAssignment {
@@ -141,6 +160,19 @@ impl VariableAssignment {
         self.assignment.iter()
     }
 
+    /// Returns the polarity that the given variable was last assigned to.
+    ///
+    /// Returns `false` if the variable has never been assigned, yet.
+    ///
+    /// # Panics
+    ///
+    /// If the variable is invalid and cannot be resolved.
+    pub fn last_value(&self, variable: Variable) -> bool {
+        self.last_values
+            .get(variable)
+            .expect("encountered unexpected invalid variable")
+    }
+
     /// Registers the given number of additional variables.
     ///
     /// # Errors
@@ -149,6 +181,9 @@ impl VariableAssignment {
     pub fn register_new_variables(&mut self, new_variables: usize) -> Result<(), Error> {
         let new_len = self.assignment.len() + new_variables;
         self.assignment.increase_capacity_to(new_len)?;
+        self.last_values
+            .increase_len(new_len)
+            .map_err(|_| Error::UsedTooManyVariables)?;
         Ok(())
     }
 
@@ -192,6 +227,9 @@ impl VariableAssignment {
             .insert(variable, assignment)
             .expect("encountered unexpected invalid variable");
         assert!(old_assignment.is_none());
+        self.last_values
+            .set(variable, assignment.to_bool())
+            .expect("encountered unexpected invalid variable");
     }
 
     /// Unassigns the given variable assignment.
@@ -209,6 +247,21 @@ impl VariableAssignment {
     }
 }
 
+/// Controls how far the search backtracks upon a conflict.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum BacktrackMode {
+    /// Backtrack directly to the asserting level computed by conflict analysis.
+    #[default]
+    NonChronological,
+    /// Backtrack only one decision level at a time, keeping assignments
+    /// above the asserting level around as long as they are not invalidated.
+    ///
+    /// This trades away some of the pruning power of non-chronological
+    /// backtracking for the ability to skip re-deriving large parts of the
+    /// search tree after conflicts close to the root.
+    Chronological,
+}
+
 /// The database combining everything that is realted to variable assignment.
 ///
 /// This holds and organizes data flows through:
@@ -224,6 +277,12 @@ pub struct Assignment {
     assignments: VariableAssignment,
     watchers: WatchList,
     propagation_queue: PropagationQueue,
+    backtrack_mode: BacktrackMode,
+    /// Implied literals discarded by the most recent backjump, together
+    /// with their reason clause, kept around in trail order so that
+    /// [`Assignment::propagate`] can cheaply re-imply them before resuming
+    /// ordinary unit propagation.
+    saved_trail: Vec<(Literal, ClauseId)>,
 }
 
 impl Assignment {
@@ -268,13 +327,49 @@ impl Assignment {
         Ok(())
     }
 
-    /// Resets the assignment to the given decision level.
-    pub fn reset_to_level(&mut self, level: DecisionLevel) {
+    /// Sets the backtracking mode used by [`Assignment::reset_to_level`].
+    pub fn set_backtrack_mode(&mut self, mode: BacktrackMode) {
+        self.backtrack_mode = mode;
+    }
+
+    /// Returns the currently configured backtracking mode.
+    pub fn backtrack_mode(&self) -> BacktrackMode {
+        self.backtrack_mode
+    }
+
+    /// Resets the assignment to the given asserting decision level.
+    ///
+    /// Under [`BacktrackMode::Chronological`] this instead backtracks only a
+    /// single decision level, i.e. to one below the current decision level,
+    /// keeping assignments above the asserting level around as long as they
+    /// are not invalidated by the backtrack. If the current decision level is
+    /// already at or below `asserting_level` (e.g. a conflict found during
+    /// root-level unit propagation, before any decision has been made), this
+    /// falls back to `asserting_level` instead of backtracking further.
+    pub fn reset_to_level(&mut self, asserting_level: DecisionLevel) {
+        let target_level = match self.backtrack_mode {
+            BacktrackMode::NonChronological => asserting_level,
+            BacktrackMode::Chronological => {
+                if self.trail.decision_level() > asserting_level {
+                    self.trail.decision_level().prev()
+                } else {
+                    asserting_level
+                }
+            }
+        };
+        let chronological = self.backtrack_mode == BacktrackMode::Chronological;
         let Self {
-            trail, assignments, ..
+            trail,
+            assignments,
+            saved_trail,
+            ..
         } = self;
-        trail.pop_to_level(level, |popped_lit| {
+        saved_trail.clear();
+        trail.pop_to_level(target_level, chronological, |popped_lit, reason| {
             assignments.unassign(popped_lit.variable());
+            if let Some(reason) = reason {
+                saved_trail.push((popped_lit, reason));
+            }
         })
     }
 
@@ -285,7 +380,8 @@ impl Assignment {
         &mut self,
         assumption: Literal,
     ) -> Result<(), EnqueueError> {
-        self.propagation_queue.push(assumption, &self.assignments)
+        self.propagation_queue
+            .push(assumption, None, &self.assignments)
     }
 }
 
@@ -293,35 +389,81 @@ impl Assignment {
 pub enum PropagationResult {
     /// Propagation led to a consistent assignment.
     Consistent,
-    /// Propagation led to a conflicting assignment.
-    Conflict,
+    /// Propagation led to a conflicting assignment, explained by the given clause.
+    Conflict(ClauseId),
 }
 
 impl PropagationResult {
     /// Returns `true` if the propagation yielded a conflict.
     pub fn is_conflict(self) -> bool {
-        matches!(self, Self::Conflict)
+        matches!(self, Self::Conflict(_))
     }
 }
 
+/// Returns `true` if `literal` is still unit under `assignment` with respect
+/// to `clause`, i.e. every other literal of the clause is already falsified.
+fn is_unit_for(clause: ClauseRef, assignment: &VariableAssignment, literal: Literal) -> bool {
+    clause
+        .into_iter()
+        .all(|other| other == literal || assignment.is_satisfied(other) == Some(false))
+}
+
 impl Assignment {
+    /// Replays as many trail-saved implications as are still valid, assigning
+    /// them directly without a watch-list scan.
+    ///
+    /// Stops and discards the remainder of the saved trail as soon as an
+    /// entry is already assigned or is no longer implied by its reason clause.
+    fn replay_saved_trail(&mut self, clause_db: &ClauseDb) {
+        let Self {
+            trail,
+            assignments,
+            saved_trail,
+            ..
+        } = self;
+        for &(literal, reason) in saved_trail.iter() {
+            if assignments.get(literal.variable()).is_some() {
+                break
+            }
+            if !is_unit_for(clause_db.resolve(reason), assignments, literal) {
+                break
+            }
+            assignments.assign(literal.variable(), literal.assignment());
+            trail.push_implied(literal, reason);
+        }
+        // Whether the loop stopped early or ran to completion, the entire
+        // saved trail is now either replayed or invalidated: either way it
+        // must be discarded before the next propagation.
+        saved_trail.clear();
+    }
+
     /// Propagates the enqueued assumptions.
     pub fn propagate(&mut self, clause_db: &mut ClauseDb) -> PropagationResult {
+        self.replay_saved_trail(&*clause_db);
         let Self {
+            trail,
             propagation_queue,
             watchers,
             assignments,
+            saved_trail,
             ..
         } = self;
-        while let Some(propagation_literal) = propagation_queue.pop() {
+        while let Some((propagation_literal, reason)) = propagation_queue.pop() {
             assignments.assign(
                 propagation_literal.variable(),
                 propagation_literal.assignment(),
             );
+            match reason {
+                Some(reason) => trail.push_implied(propagation_literal, reason),
+                None => {
+                    trail.push_decision(propagation_literal);
+                    saved_trail.clear();
+                }
+            }
             let result = watchers.propagate(
                 propagation_literal,
                 clause_db,
-                &assignments,
+                assignments,
                 PropagationEnqueuer::new(propagation_queue),
             );
             if result.is_conflict() {
@@ -330,6 +472,141 @@ impl Assignment {
         }
         PropagationResult::Consistent
     }
+
+    /// Analyzes the given conflicting clause and derives a learnt clause via
+    /// first unique implication point (1-UIP) conflict analysis.
+    ///
+    /// Returns the learnt clause together with the decision level to
+    /// backjump to, i.e. the second-highest decision level among the learnt
+    /// clause's literals, or the top level if the learnt clause is unit.
+    pub fn analyze_conflict(
+        &self,
+        clause_db: &ClauseDb,
+        conflict: ClauseId,
+    ) -> (Vec<Literal>, DecisionLevel) {
+        let current_level = self.trail.decision_level();
+        let mut seen = vec![false; self.len_variables()];
+        let mut learnt = Vec::new();
+        let mut num_at_current_level = 0;
+        let mut clause_literals: Vec<Literal> = clause_db.resolve(conflict).into_iter().collect();
+        let mut trail_iter = self.trail.iter().rev();
+        let mut uip: Option<Literal> = None;
+        loop {
+            for literal in clause_literals.drain(..) {
+                let variable = literal.variable();
+                if Some(literal) == uip || seen[variable.into_index()] {
+                    continue
+                }
+                seen[variable.into_index()] = true;
+                let (level, _) = self
+                    .trail
+                    .level_and_reason_of(variable)
+                    .expect("encountered unexpected unassigned variable in reason clause");
+                if level == current_level {
+                    num_at_current_level += 1;
+                } else {
+                    learnt.push(literal);
+                }
+            }
+            let entry = trail_iter
+                .by_ref()
+                .find(|entry| seen[entry.literal().variable().into_index()])
+                .expect("encountered conflict analysis running past the decision level");
+            uip = Some(entry.literal());
+            num_at_current_level -= 1;
+            if num_at_current_level == 0 {
+                break
+            }
+            let reason = entry
+                .reason()
+                .expect("marked literal below the 1-UIP must have a reason");
+            clause_literals = clause_db.resolve(reason).into_iter().collect();
+        }
+        let uip = uip.expect("encountered conflict analysis without any 1-UIP literal");
+        learnt.push(!uip);
+        let backjump_level = learnt[..learnt.len() - 1]
+            .iter()
+            .map(|&literal| {
+                self.trail
+                    .level_and_reason_of(literal.variable())
+                    .expect("encountered unexpected unassigned variable in learnt clause")
+                    .0
+            })
+            .max()
+            .unwrap_or_else(DecisionLevel::top);
+        (learnt, backjump_level)
+    }
+
+    /// Computes the subset of decision literals that participated in the
+    /// conflict seeded by `conflicting_literals`.
+    ///
+    /// This reuses the marking machinery of [`Assignment::analyze_conflict`]
+    /// but, instead of stopping at the first unique implication point, walks
+    /// the trail all the way back to the root, collecting the negation of
+    /// every marked decision literal it passes. Since assumptions are always
+    /// pushed as decision literals, this yields exactly the assumptions that
+    /// are jointly responsible for the conflict.
+    fn analyze_final(
+        &self,
+        clause_db: &ClauseDb,
+        conflicting_literals: impl IntoIterator<Item = Literal>,
+    ) -> Vec<Literal> {
+        let mut seen = vec![false; self.len_variables()];
+        let mut core = Vec::new();
+        for literal in conflicting_literals {
+            seen[literal.variable().into_index()] = true;
+        }
+        for entry in self.trail.iter().rev() {
+            let variable = entry.literal().variable();
+            if !seen[variable.into_index()] {
+                continue
+            }
+            match entry.reason() {
+                None => core.push(!entry.literal()),
+                Some(reason) => {
+                    for literal in clause_db.resolve(reason) {
+                        seen[literal.variable().into_index()] = true;
+                    }
+                }
+            }
+        }
+        core
+    }
+
+    /// Enqueues and propagates the given assumptions in order, each pushed
+    /// as its own decision.
+    ///
+    /// Returns `Ok(())` if all assumptions could be consistently propagated.
+    /// Returns `Err(core)` as soon as an assumption conflicts, where `core`
+    /// is the minimal-effort UNSAT core: the negation of exactly the
+    /// assumptions that jointly caused the conflict.
+    pub fn solve_under_assumptions(
+        &mut self,
+        assumptions: impl IntoIterator<Item = Literal>,
+        clause_db: &mut ClauseDb,
+    ) -> Result<(), Vec<Literal>> {
+        for assumption in assumptions {
+            match self.enqueue_assumption(assumption) {
+                Ok(()) => (),
+                Err(error) => {
+                    if error.is_conflict() {
+                        // The failing assumption never made it onto the trail,
+                        // so its negation is added to the core explicitly.
+                        let mut core = self.analyze_final(clause_db, iter::once(!assumption));
+                        core.push(!assumption);
+                        return Err(core)
+                    }
+                    continue
+                }
+            }
+            if let PropagationResult::Conflict(conflict) = self.propagate(clause_db) {
+                let conflict_literals: Vec<Literal> =
+                    clause_db.resolve(conflict).into_iter().collect();
+                return Err(self.analyze_final(clause_db, conflict_literals))
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<'a> IntoIterator for &'a Assignment {
@@ -361,4 +638,168 @@ impl<'a> Iterator for Iter<'a> {
             .next()
             .map(|(variable, assignment)| (variable, *assignment))
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(index: usize) -> Variable {
+        Variable::from_index(index).expect("encountered unexpected invalid variable index")
+    }
+
+    fn pos(index: usize) -> Literal {
+        Literal::new(var(index), true)
+    }
+
+    fn neg(index: usize) -> Literal {
+        Literal::new(var(index), false)
+    }
+
+    fn assignment_with_variables(len_variables: usize) -> Assignment {
+        let mut assignment = Assignment::default();
+        assignment
+            .register_new_variables(len_variables)
+            .expect("encountered unexpected out of bounds variable count");
+        assignment
+    }
+
+    #[test]
+    fn analyze_conflict_resolves_across_two_decision_levels() {
+        let mut assignment = assignment_with_variables(4);
+        let mut clause_db = ClauseDb::default();
+
+        let a = pos(0);
+        let b = pos(1);
+        let c = pos(2);
+        let d = pos(3);
+
+        assignment.trail.push_decision(a);
+        let level_of_a = assignment.trail.decision_level();
+        assignment.trail.push_decision(b);
+
+        let reason_c = clause_db.insert([neg(1), c]);
+        let reason_d = clause_db.insert([neg(1), d]);
+        assignment.trail.push_implied(c, reason_c);
+        assignment.trail.push_implied(d, reason_d);
+
+        let conflict = clause_db.insert([neg(2), neg(3), neg(0)]);
+        let (learnt, backjump_level) = assignment.analyze_conflict(&clause_db, conflict);
+
+        assert_eq!(learnt, vec![neg(0), neg(1)]);
+        assert_eq!(backjump_level, level_of_a);
+    }
+
+    #[test]
+    fn analyze_conflict_resolves_to_unit_clause_at_root_level() {
+        let mut assignment = assignment_with_variables(1);
+        let mut clause_db = ClauseDb::default();
+
+        let a = pos(0);
+        let reason_a = clause_db.insert([a]);
+        assignment.trail.push_implied(a, reason_a);
+
+        let conflict = clause_db.insert([neg(0)]);
+        let (learnt, backjump_level) = assignment.analyze_conflict(&clause_db, conflict);
+
+        assert_eq!(learnt, vec![neg(0)]);
+        assert_eq!(backjump_level, DecisionLevel::top());
+    }
+
+    #[test]
+    fn reset_to_level_chronological_at_root_does_not_panic() {
+        let mut assignment = assignment_with_variables(1);
+        assignment.set_backtrack_mode(BacktrackMode::Chronological);
+
+        assignment.reset_to_level(DecisionLevel::top());
+
+        assert_eq!(assignment.trail.decision_level(), DecisionLevel::top());
+    }
+
+    #[test]
+    fn reset_to_level_chronological_backjumps_a_single_level_keeping_survivors() {
+        let mut assignment = assignment_with_variables(3);
+        assignment.set_backtrack_mode(BacktrackMode::Chronological);
+
+        let a = pos(0);
+        assignment.trail.push_decision(a);
+        assignment.assignments.assign(a.variable(), a.assignment());
+
+        let b = pos(1);
+        assignment.trail.push_decision(b);
+        assignment.assignments.assign(b.variable(), b.assignment());
+        let level_after_b = assignment.trail.decision_level();
+
+        let c = pos(2);
+        assignment.trail.push_decision(c);
+        assignment.assignments.assign(c.variable(), c.assignment());
+
+        assignment.reset_to_level(DecisionLevel::top());
+
+        assert_eq!(assignment.trail.decision_level(), level_after_b);
+        assert_eq!(
+            assignment.assignments.get(a.variable()),
+            Some(a.assignment())
+        );
+        assert_eq!(
+            assignment.assignments.get(b.variable()),
+            Some(b.assignment())
+        );
+        assert_eq!(assignment.assignments.get(c.variable()), None);
+    }
+
+    #[test]
+    fn replay_saved_trail_discards_entire_remainder_on_first_invalidated_entry() {
+        let mut assignment = assignment_with_variables(4);
+        let mut clause_db = ClauseDb::default();
+
+        let x = pos(0);
+        let a = pos(1);
+        let b = pos(2);
+        let d = pos(3);
+
+        assignment.trail.push_decision(x);
+        assignment.assignments.assign(x.variable(), x.assignment());
+
+        let reason_a = clause_db.insert([neg(0), a]);
+        let reason_b = clause_db.insert([a, b]);
+        let reason_d = clause_db.insert([neg(0), d]);
+
+        assignment.saved_trail = vec![(a, reason_a), (b, reason_b), (d, reason_d)];
+
+        assignment.replay_saved_trail(&clause_db);
+
+        assert!(assignment.saved_trail.is_empty());
+        assert_eq!(
+            assignment.assignments.get(a.variable()),
+            Some(VarAssignment::True)
+        );
+        assert_eq!(assignment.assignments.get(b.variable()), None);
+        assert_eq!(assignment.assignments.get(d.variable()), None);
+    }
+
+    #[test]
+    fn solve_under_assumptions_detects_conflict_at_enqueue_time() {
+        let mut assignment = assignment_with_variables(1);
+        let mut clause_db = ClauseDb::default();
+
+        let result = assignment.solve_under_assumptions([neg(0), pos(0)], &mut clause_db);
+
+        assert_eq!(result, Err(vec![pos(0), neg(0)]));
+    }
+
+    #[test]
+    fn solve_under_assumptions_detects_conflict_after_propagation() {
+        let mut assignment = assignment_with_variables(2);
+        let mut clause_db = ClauseDb::default();
+
+        let c1 = clause_db.insert([neg(0), pos(1)]);
+        let c2 = clause_db.insert([neg(0), neg(1)]);
+        assignment.initialize_watchers(clause_db.resolve(c1));
+        assignment.initialize_watchers(clause_db.resolve(c2));
+
+        let result = assignment.solve_under_assumptions([pos(0)], &mut clause_db);
+
+        assert_eq!(result, Err(vec![neg(0)]));
+    }
 }
\ No newline at end of file
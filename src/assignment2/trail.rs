@@ -0,0 +1,195 @@
+use crate::{
+    clause_db::ClauseId,
+    utils::BoundedMap,
+    Error,
+    Literal,
+    Variable,
+};
+
+/// A decision level within the search tree.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DecisionLevel(usize);
+
+impl DecisionLevel {
+    /// The top-level decision level before any decision has been made.
+    pub const fn top() -> Self {
+        Self(0)
+    }
+
+    /// Returns the next higher decision level.
+    fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+
+    /// Returns the previous, lower decision level.
+    ///
+    /// # Panics
+    ///
+    /// If `self` is already the top-level decision level.
+    pub(crate) fn prev(self) -> Self {
+        self.0
+            .checked_sub(1)
+            .map(Self)
+            .expect("cannot decrement the top-level decision level")
+    }
+}
+
+/// A single entry of the trail.
+///
+/// Besides the assigned literal this also stores the decision level at
+/// which the literal was assigned as well as its propagation reason: `None`
+/// for decision literals, `Some` for literals that were implied by unit
+/// propagation.
+#[derive(Debug, Copy, Clone)]
+pub struct TrailEntry {
+    literal: Literal,
+    decision_level: DecisionLevel,
+    reason: Option<ClauseId>,
+}
+
+impl TrailEntry {
+    /// Returns the literal assigned by this trail entry.
+    pub fn literal(&self) -> Literal {
+        self.literal
+    }
+
+    /// Returns the decision level at which the literal was assigned.
+    pub fn decision_level(&self) -> DecisionLevel {
+        self.decision_level
+    }
+
+    /// Returns the reason clause that implied the literal, if any.
+    ///
+    /// Returns `None` if the literal is a decision literal.
+    pub fn reason(&self) -> Option<ClauseId> {
+        self.reason
+    }
+}
+
+/// The trail of assigned literals in chronological order.
+///
+/// Besides the plain sequence of assigned literals this also tracks, per
+/// variable, the decision level it was assigned at and the clause that
+/// forced it, so that conflicts can later be explained via conflict
+/// analysis.
+#[derive(Debug, Default, Clone)]
+pub struct Trail {
+    entries: Vec<TrailEntry>,
+    /// Decision level and reason of the variable that the trail entry was
+    /// assigned for, indexed by variable.
+    var_info: BoundedMap<Variable, (DecisionLevel, Option<ClauseId>)>,
+    decision_level: DecisionLevel,
+}
+
+impl Trail {
+    /// Registers the given number of additional variables.
+    ///
+    /// # Errors
+    ///
+    /// If the number of total variables is out of supported bounds.
+    pub fn register_new_variables(&mut self, new_variables: usize) -> Result<(), Error> {
+        self.entries.reserve(new_variables);
+        let new_len = self.var_info.len() + new_variables;
+        self.var_info.increase_capacity_to(new_len)?;
+        Ok(())
+    }
+
+    /// Returns the current decision level.
+    pub fn decision_level(&self) -> DecisionLevel {
+        self.decision_level
+    }
+
+    /// Returns the number of assigned literals on the trail.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns an iterator over the trail entries in chronological order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &TrailEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns the decision level and reason that the given variable was
+    /// assigned with, if it is currently assigned.
+    pub fn level_and_reason_of(
+        &self,
+        variable: Variable,
+    ) -> Option<(DecisionLevel, Option<ClauseId>)> {
+        self.var_info
+            .get(variable)
+            .expect("encountered unexpected invalid variable")
+            .copied()
+    }
+
+    /// Pushes a new decision literal onto the trail, opening a new decision level.
+    pub fn push_decision(&mut self, literal: Literal) {
+        self.decision_level = self.decision_level.next();
+        self.push(literal, None);
+    }
+
+    /// Pushes a new implied literal onto the trail at the current decision level.
+    pub fn push_implied(&mut self, literal: Literal, reason: ClauseId) {
+        self.push(literal, Some(reason));
+    }
+
+    /// Shared implementation for pushing a new trail entry.
+    fn push(&mut self, literal: Literal, reason: Option<ClauseId>) {
+        let decision_level = self.decision_level;
+        self.entries.push(TrailEntry {
+            literal,
+            decision_level,
+            reason,
+        });
+        self.var_info
+            .insert(literal.variable(), (decision_level, reason))
+            .expect("encountered unexpected invalid variable");
+    }
+
+    /// Unassigns every trail entry whose decision level is strictly above
+    /// the given decision level, calling `popped` with the literal and its
+    /// reason (if any) for each of them, in trail order.
+    ///
+    /// Under non-chronological backtracking the entries above `level` are
+    /// guaranteed to form a contiguous suffix of the trail, so `chronological`
+    /// should be `false` to pop them in cheap `O(popped)` time. Pass `true`
+    /// only under [`BacktrackMode::Chronological`], where the trail is no
+    /// longer guaranteed to hold entries in strictly increasing
+    /// decision-level order and a full `O(len)` scan is required to find
+    /// exactly those entries while preserving the relative order of the
+    /// surviving ones.
+    ///
+    /// [`BacktrackMode::Chronological`]: super::BacktrackMode::Chronological
+    pub fn pop_to_level(
+        &mut self,
+        level: DecisionLevel,
+        chronological: bool,
+        mut popped: impl FnMut(Literal, Option<ClauseId>),
+    ) {
+        let var_info = &mut self.var_info;
+        if chronological {
+            self.entries.retain(|entry| {
+                if entry.decision_level <= level {
+                    return true
+                }
+                var_info
+                    .take(entry.literal.variable())
+                    .expect("encountered unexpected invalid variable");
+                popped(entry.literal, entry.reason);
+                false
+            });
+        } else {
+            let keep = self
+                .entries
+                .iter()
+                .rposition(|entry| entry.decision_level <= level)
+                .map_or(0, |index| index + 1);
+            for entry in self.entries.drain(keep..) {
+                var_info
+                    .take(entry.literal.variable())
+                    .expect("encountered unexpected invalid variable");
+                popped(entry.literal, entry.reason);
+            }
+        }
+        self.decision_level = level;
+    }
+}
@@ -0,0 +1,96 @@
+use super::{
+    PropagationEnqueuer,
+    PropagationResult,
+    VariableAssignment,
+};
+use crate::{
+    clause_db::ClauseId,
+    ClauseDb,
+    Error,
+    Literal,
+};
+
+/// The two-watched-literals scheme used to drive unit propagation.
+///
+/// For every literal this keeps the list of clauses that currently watch
+/// it. Whenever a literal is falsified, only the clauses watching it need
+/// to be inspected to find a new watch, detect a unit clause, or detect a
+/// conflict.
+#[derive(Debug, Default, Clone)]
+pub struct WatchList {
+    /// The clauses that watch a given literal, indexed by `literal.into_index()`.
+    watchers: Vec<Vec<ClauseId>>,
+}
+
+impl WatchList {
+    /// Registers the given total number of variables.
+    ///
+    /// # Errors
+    ///
+    /// If the number of total variables is out of supported bounds.
+    pub fn register_new_variables(&mut self, total_variables: usize) -> Result<(), Error> {
+        self.watchers.resize(total_variables * 2, Vec::new());
+        Ok(())
+    }
+
+    /// Registers the given clause as a watcher of the given literal.
+    pub fn register_for_lit(&mut self, literal: Literal, clause: ClauseId) {
+        self.watchers[literal.into_index()].push(clause);
+    }
+
+    /// Propagates the given newly assigned literal.
+    ///
+    /// Scans all clauses watching the negation of `propagated` and either
+    /// finds them a new watch, enqueues a newly implied literal together
+    /// with its reason clause, or reports the clause as a conflict.
+    pub fn propagate(
+        &mut self,
+        propagated: Literal,
+        clause_db: &mut ClauseDb,
+        assignment: &VariableAssignment,
+        mut enqueuer: PropagationEnqueuer,
+    ) -> PropagationResult {
+        let falsified = !propagated;
+        let mut i = 0;
+        'watchers: while i < self.watchers[falsified.into_index()].len() {
+            let clause_id = self.watchers[falsified.into_index()][i];
+            let clause = clause_db.resolve(clause_id);
+            let mut unresolved = None;
+            for literal in clause {
+                match assignment.is_satisfied(literal) {
+                    Some(true) => {
+                        // The clause is already satisfied by some other literal.
+                        i += 1;
+                        continue 'watchers
+                    }
+                    Some(false) => continue,
+                    None => {
+                        match unresolved {
+                            None => unresolved = Some(literal),
+                            Some(_) => {
+                                // A second unresolved literal: move the watch there.
+                                let new_watch = literal;
+                                self.watchers[falsified.into_index()].swap_remove(i);
+                                self.watchers[new_watch.into_index()].push(clause_id);
+                                continue 'watchers
+                            }
+                        }
+                    }
+                }
+            }
+            i += 1;
+            match unresolved {
+                Some(implied_literal) => {
+                    // Errors are impossible here: `implied_literal` was just
+                    // found unresolved under `assignment`, so it can be
+                    // neither already satisfied nor conflicting.
+                    enqueuer
+                        .push(implied_literal, clause_id, assignment)
+                        .expect("encountered unexpected already resolved literal");
+                }
+                None => return PropagationResult::Conflict(clause_id),
+            }
+        }
+        PropagationResult::Consistent
+    }
+}